@@ -3,19 +3,22 @@ use std::io::Write;
 use rust_decimal::Decimal;
 use serde::Serialize;
 
-use crate::db::{Database, ClientId};
+use crate::db::{AssetId, ClientId, Database};
 
 pub fn write(writer: impl Write, db: &Database) -> anyhow::Result<()> {
     let mut writer = csv::Writer::from_writer(writer);
 
     for client in db.clients.values() {
-        writer.serialize(Account {
-            client: client.id,
-            available: client.available,
-            held: client.held,
-            total: client.available + client.held,
-            locked: client.locked,
-        })?;
+        for (asset, balance) in &client.balances {
+            writer.serialize(Account {
+                client: client.id,
+                asset: asset.clone(),
+                available: balance.available,
+                held: balance.held,
+                total: balance.available + balance.held,
+                locked: client.locked,
+            })?;
+        }
     }
 
     Ok(())
@@ -24,6 +27,7 @@ pub fn write(writer: impl Write, db: &Database) -> anyhow::Result<()> {
 #[derive(Serialize)]
 struct Account {
     client: ClientId,
+    asset: AssetId,
     available: Decimal,
     held: Decimal,
     total: Decimal,
@@ -0,0 +1,328 @@
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+use csv::Trim;
+use rust_decimal::Decimal;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+
+use crate::db::{AssetId, ClientId, Database, TxId, DEFAULT_ASSET};
+
+/// One entry from the transaction file.
+#[derive(Debug)]
+pub enum Record {
+    /// A deposit into a client's account.
+    Deposit {
+        client: ClientId,
+        tx: TxId,
+        asset: AssetId,
+        amount: Decimal,
+    },
+
+    /// A withdrawal from a client's account.
+    Withdrawal {
+        client: ClientId,
+        tx: TxId,
+        asset: AssetId,
+        amount: Decimal,
+    },
+
+    /// A transfer of funds from one client's account to another's.
+    Transfer {
+        from: ClientId,
+        to: ClientId,
+        tx: TxId,
+        asset: AssetId,
+        amount: Decimal,
+    },
+
+    /// A dispute of a previous transaction. Funds are held until the dispute is resolved or charged
+    /// back.
+    Dispute { client: ClientId, tx: TxId },
+
+    /// Resolves a previous dispute, lifting the hold.
+    Resolve { client: ClientId, tx: TxId },
+
+    /// Resolves a previous dispute by withdrawing held funds and freezing the client's account.
+    Chargeback { client: ClientId, tx: TxId },
+}
+
+impl Record {
+    /// Applies this record to `db`.
+    pub fn apply(&self, db: &mut Database) -> anyhow::Result<()> {
+        match self {
+            Record::Deposit {
+                client,
+                tx,
+                asset,
+                amount,
+            } => db.deposit(*client, *tx, asset.clone(), *amount),
+            Record::Withdrawal {
+                client,
+                tx,
+                asset,
+                amount,
+            } => db.withdraw(*client, *tx, asset.clone(), *amount),
+            Record::Transfer {
+                from,
+                to,
+                tx,
+                asset,
+                amount,
+            } => db.transfer(*from, *to, *tx, asset.clone(), *amount),
+            Record::Dispute { client, tx } => db.dispute(*client, *tx),
+            Record::Resolve { client, tx } => db.resolve(*client, *tx),
+            Record::Chargeback { client, tx } => db.chargeback(*client, *tx),
+        }
+        .map_err(Into::into)
+    }
+}
+
+/// A record was missing a field its type requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordError {
+    /// A deposit, withdrawal, or transfer didn't include an `amount`.
+    MissingAmount,
+    /// A transfer didn't include a `to` client.
+    MissingDestination,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::MissingAmount => write!(f, "missing amount"),
+            RecordError::MissingDestination => write!(f, "missing destination client"),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// I couldn't get `serde` to deserialize [`Record`] objects directly. According to @BurntSushi,
+/// [tagged enums and CSVs don't play nicely][1]. As a workaround, I use `serde` to deserialize
+/// `RawRecord`s and then turn those into `Record`s with some handwritten code.
+///
+/// [1]: https://github.com/BurntSushi/rust-csv/issues/211
+#[derive(Deserialize, Debug)]
+struct RawRecord {
+    r#type: RawRecordType,
+    client: ClientId,
+    tx: TxId,
+    /// Omitted entirely by input files that predate multi-currency support, in which case the
+    /// record falls back to [`DEFAULT_ASSET`].
+    asset: Option<AssetId>,
+    #[serde(default, deserialize_with = "deserialize_amount")]
+    amount: Option<Decimal>,
+    /// The destination client of a [`RawRecordType::Transfer`]. Absent for every other record type.
+    to: Option<ClientId>,
+}
+
+/// Parses the `amount` column through [`Decimal::from_str`] rather than relying on `rust_decimal`'s
+/// own `Deserialize` impl, which accepts forms (like floats) that can silently lose precision
+/// before they ever reach us. Rejects amounts with more than 4 fractional digits outright rather
+/// than rounding them off, since silently discarding digits from a currency amount would make the
+/// ledger wrong in a way nobody could detect from the output alone.
+fn deserialize_amount<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: Option<&str> = Option::deserialize(deserializer)?;
+    let raw = match raw {
+        Some(raw) if !raw.is_empty() => raw,
+        _ => return Ok(None),
+    };
+
+    let amount = Decimal::from_str(raw)
+        .map_err(|err| de::Error::custom(format!("invalid amount {:?}: {}", raw, err)))?;
+    if amount.scale() > 4 {
+        return Err(de::Error::custom(format!(
+            "amount {} has more than 4 decimal places",
+            amount
+        )));
+    }
+    Ok(Some(amount))
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+enum RawRecordType {
+    Deposit,
+    Withdrawal,
+    Transfer,
+    Dispute,
+    Resolve,
+    Chargeback,
+}
+
+impl TryFrom<RawRecord> for Record {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawRecord) -> Result<Self, Self::Error> {
+        match raw.r#type {
+            RawRecordType::Deposit => Ok(Record::Deposit {
+                client: raw.client,
+                tx: raw.tx,
+                asset: raw.asset.unwrap_or_else(|| DEFAULT_ASSET.to_string()),
+                amount: raw.amount.ok_or(RecordError::MissingAmount)?,
+            }),
+            RawRecordType::Withdrawal => Ok(Record::Withdrawal {
+                client: raw.client,
+                tx: raw.tx,
+                asset: raw.asset.unwrap_or_else(|| DEFAULT_ASSET.to_string()),
+                amount: raw.amount.ok_or(RecordError::MissingAmount)?,
+            }),
+            RawRecordType::Transfer => Ok(Record::Transfer {
+                from: raw.client,
+                to: raw.to.ok_or(RecordError::MissingDestination)?,
+                tx: raw.tx,
+                asset: raw.asset.unwrap_or_else(|| DEFAULT_ASSET.to_string()),
+                amount: raw.amount.ok_or(RecordError::MissingAmount)?,
+            }),
+            RawRecordType::Dispute => Ok(Record::Dispute {
+                client: raw.client,
+                tx: raw.tx,
+            }),
+            RawRecordType::Resolve => Ok(Record::Resolve {
+                client: raw.client,
+                tx: raw.tx,
+            }),
+            RawRecordType::Chargeback => Ok(Record::Chargeback {
+                client: raw.client,
+                tx: raw.tx,
+            }),
+        }
+    }
+}
+
+/// Returns an iterator over all of the transaction records read from `reader`, read one line at a
+/// time rather than all at once.
+///
+/// This keeps peak memory bounded by the size of a single record (plus whatever the caller
+/// accumulates in a [`Database`]) instead of the size of the whole file, so a multi-hundred-MB CSV
+/// can be processed without buffering it first. Each item carries its own `Result`, so a malformed
+/// line doesn't have to abort lines read before it.
+pub fn read(reader: impl Read) -> impl Iterator<Item = anyhow::Result<Record>> {
+    csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(reader)
+        .into_deserialize()
+        .map(|raw_record| -> anyhow::Result<Record> {
+            let raw_record: RawRecord = raw_record?;
+            raw_record.try_into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn read_streams_records_one_at_a_time() {
+        let csv = "type,client,tx,amount\n\
+                   deposit,1,1,10\n\
+                   deposit,1,2,20\n\
+                   deposit,1,3,30\n";
+        let mut db = Database::new();
+        let mut records = read(Cursor::new(csv));
+
+        // Apply just the first record the iterator hands back, proving a caller can interleave
+        // reading and applying instead of having to collect the whole file into memory first.
+        records.next().unwrap().unwrap().apply(&mut db).unwrap();
+        assert_eq!(
+            db.clients[&1].balances[DEFAULT_ASSET].available,
+            dec!(10)
+        );
+
+        for record in records {
+            record.unwrap().apply(&mut db).unwrap();
+        }
+        assert_eq!(
+            db.clients[&1].balances[DEFAULT_ASSET].available,
+            dec!(60)
+        );
+    }
+
+    fn read_all(csv: &str) -> anyhow::Result<Vec<Record>> {
+        read(Cursor::new(csv)).collect()
+    }
+
+    #[test]
+    fn read_defaults_asset_when_column_is_missing() {
+        let records = read_all("type,client,tx,amount\ndeposit,1,1,10\n").unwrap();
+        assert!(matches!(
+            &records[..],
+            [Record::Deposit { asset, amount, .. }] if asset == DEFAULT_ASSET && *amount == dec!(10)
+        ));
+    }
+
+    #[test]
+    fn read_parses_the_asset_column() {
+        let records = read_all("type,client,tx,asset,amount\ndeposit,1,1,eth,10\n").unwrap();
+        assert!(matches!(
+            &records[..],
+            [Record::Deposit { asset, amount, .. }] if asset == "eth" && *amount == dec!(10)
+        ));
+    }
+
+    #[test]
+    fn read_parses_transfer_and_its_to_column() {
+        let records =
+            read_all("type,client,tx,asset,amount,to\ntransfer,1,1,eth,4,2\n").unwrap();
+        assert!(matches!(
+            &records[..],
+            [Record::Transfer { from: 1, to: 2, asset, amount, .. }]
+                if asset == "eth" && *amount == dec!(4)
+        ));
+    }
+
+    #[test]
+    fn read_rejects_amounts_with_more_than_4_decimal_places() {
+        let err = read_all("type,client,tx,amount\ndeposit,1,1,1.00001\n").unwrap_err();
+        assert!(err.to_string().contains("more than 4 decimal places"));
+    }
+
+    #[test]
+    fn read_accepts_amounts_with_up_to_4_decimal_places() {
+        let records = read_all("type,client,tx,amount\ndeposit,1,1,1.0001\n").unwrap();
+        assert!(matches!(
+            &records[..],
+            [Record::Deposit { amount, .. }] if *amount == dec!(1.0001)
+        ));
+    }
+
+    #[test]
+    fn read_rejects_deposit_missing_amount() {
+        let err = read_all("type,client,tx,amount\ndeposit,1,1,\n").unwrap_err();
+        assert!(err.to_string().contains("missing amount"));
+    }
+
+    #[test]
+    fn read_rejects_transfer_missing_destination() {
+        let err = read_all("type,client,tx,amount\ntransfer,1,1,10\n").unwrap_err();
+        assert!(err.to_string().contains("missing destination"));
+    }
+
+    #[test]
+    fn read_trims_whitespace_and_tolerates_ragged_rows() {
+        // Dispute/resolve/chargeback rows carry only `type,client,tx`, so `flexible(true)` has to
+        // accept rows shorter than the deposit/withdrawal header, and `Trim::All` has to strip the
+        // stray spaces around every field (including the header names themselves).
+        let records = read_all(
+            "type, client, tx, amount\n deposit , 1 , 1 , 10 \n dispute , 1 , 1 \n",
+        )
+        .unwrap();
+        assert!(matches!(
+            &records[..],
+            [
+                Record::Deposit { client: 1, tx: 1, amount, .. },
+                Record::Dispute { client: 1, tx: 1 },
+            ] if *amount == dec!(10)
+        ));
+    }
+}
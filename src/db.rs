@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
-use anyhow::{anyhow, ensure, Context};
+use anyhow::ensure;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
@@ -10,12 +11,24 @@ pub type ClientId = u16;
 /// Transaction ID.
 pub type TxId = u32;
 
-/// A client's funds and account status.
+/// Asset (currency) identifier, e.g. `"BTC"` or `"USD"`.
+pub type AssetId = String;
+
+/// The asset assumed for input that predates multi-currency support and carries no asset column.
+pub const DEFAULT_ASSET: &str = "default";
+
+/// A client's available and held funds in a single asset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Balance {
+    pub available: Decimal,
+    pub held: Decimal,
+}
+
+/// A client's per-asset funds and account-wide status.
 #[derive(Debug)]
 pub struct Client {
     pub id: ClientId,
-    pub available: Decimal,
-    pub held: Decimal,
+    pub balances: HashMap<AssetId, Balance>,
     pub locked: bool,
 }
 
@@ -23,106 +36,246 @@ impl Client {
     pub fn new(id: ClientId) -> Self {
         Client {
             id,
-            available: dec!(0),
-            held: dec!(0),
+            balances: HashMap::new(),
             locked: false,
         }
     }
 
-    pub fn deposit(&mut self, amount: Decimal) -> anyhow::Result<()> {
+    pub fn deposit(&mut self, asset: &AssetId, amount: Decimal) -> anyhow::Result<()> {
         ensure!(amount >= dec!(0), "negative deposit: {}", amount);
-        self.available += amount;
+        self.balance(asset).available += amount;
         Ok(())
     }
 
-    pub fn withdraw(&mut self, amount: Decimal) -> anyhow::Result<()> {
+    pub fn withdraw(&mut self, asset: &AssetId, amount: Decimal) -> anyhow::Result<()> {
         ensure!(amount >= dec!(0), "negative withdrawal: {}", amount);
+        let balance = self.balance(asset);
         ensure!(
-            amount <= self.available,
+            amount <= balance.available,
             "cannot withdraw {}, only {} available",
             amount,
-            self.available
+            balance.available
         );
-        self.available -= amount;
+        balance.available -= amount;
         Ok(())
     }
 
-    pub fn hold(&mut self, amount: Decimal) -> anyhow::Result<()> {
+    pub fn hold(&mut self, asset: &AssetId, amount: Decimal) -> anyhow::Result<()> {
         ensure!(amount >= dec!(0), "negative hold: {}", amount);
-        self.available -= amount;
-        self.held += amount;
+        let balance = self.balance(asset);
+        ensure!(
+            amount <= balance.available,
+            "cannot hold {}, only {} available",
+            amount,
+            balance.available
+        );
+        balance.available -= amount;
+        balance.held += amount;
         Ok(())
     }
 
-    pub fn release(&mut self, amount: Decimal) -> anyhow::Result<()> {
+    /// Provisionally credits the client by `amount`, as though the dispute might reverse a
+    /// withdrawal that already left the account. Unlike [`Client::hold`], this doesn't move
+    /// existing `available` funds into `held`; it adds new held funds, since the withdrawn amount
+    /// isn't sitting in `available` to begin with.
+    pub fn hold_withdrawal(&mut self, asset: &AssetId, amount: Decimal) -> anyhow::Result<()> {
+        ensure!(amount >= dec!(0), "negative hold: {}", amount);
+        self.balance(asset).held += amount;
+        Ok(())
+    }
+
+    pub fn release(&mut self, asset: &AssetId, amount: Decimal) -> anyhow::Result<()> {
         ensure!(amount >= dec!(0), "negative release: {}", amount);
+        let balance = self.balance(asset);
         ensure!(
-            amount <= self.held,
+            amount <= balance.held,
             "cannot release {}, only {} held",
             amount,
-            self.held
+            balance.held
         );
-        self.available += amount;
-        self.held -= amount;
+        balance.available += amount;
+        balance.held -= amount;
         Ok(())
     }
 
-    pub fn chargeback(&mut self, amount: Decimal) -> anyhow::Result<()> {
+    pub fn chargeback(&mut self, asset: &AssetId, amount: Decimal) -> anyhow::Result<()> {
         ensure!(amount >= dec!(0), "negative chargeback: {}", amount);
+        let balance = self.balance(asset);
         ensure!(
-            amount <= self.held,
+            amount <= balance.held,
             "cannot chargeback {}, only {} held",
             amount,
-            self.held
+            balance.held
         );
-        self.held -= amount;
+        balance.held -= amount;
         self.locked = true;
         Ok(())
     }
+
+    /// Look up an asset's balance, or create a new zeroed one.
+    fn balance(&mut self, asset: &AssetId) -> &mut Balance {
+        self.balances.entry(asset.clone()).or_default()
+    }
 }
 
-/// A deposit or withdrawal. A positive `amount` is a deposit, negative a withdrawal.
+/// A deposit, withdrawal, or transfer. A positive `amount` is a deposit, negative a withdrawal or
+/// an outgoing transfer; `kind` is what actually distinguishes them, since a dispute needs to know
+/// whether to credit or debit the client while it's outstanding, and whether it's disputable at
+/// all.
 #[derive(Debug)]
 pub struct Tx {
-    pub id: TxId,
+    /// The client that owns this transaction. Disputes, resolves, and chargebacks must reference
+    /// this same client, not just any client with a balance of their own.
+    pub client: ClientId,
+    pub asset: AssetId,
     pub amount: Decimal,
+    pub kind: TxKind,
+    pub state: TxState,
 }
 
 impl Tx {
-    pub fn new(id: TxId, amount: Decimal) -> Self {
-        Tx { id, amount }
+    pub fn new(client: ClientId, asset: AssetId, amount: Decimal, kind: TxKind) -> Self {
+        Tx {
+            client,
+            asset,
+            amount,
+            kind,
+            state: TxState::Processed,
+        }
     }
 }
 
+/// What kind of movement a [`Tx`] recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxKind {
+    Deposit,
+    Withdrawal,
+    /// An outgoing transfer. Unlike a withdrawal, a transfer can never be disputed: the funds are
+    /// still inside the ledger, under the destination client's control, rather than having left it.
+    Transfer,
+}
+
+/// A transaction starts out `Processed`. From there it can be `Disputed`, and from `Disputed` it
+/// resolves one way or the other: back to `Resolved` or forward to `ChargedBack`. Any other
+/// transition, such as disputing a transaction twice or resolving one that was never disputed, is
+/// rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxState {
+    Processed,
+    Disputed,
+    Resolved,
+    ChargedBack,
+}
+
+/// A transaction record was rejected. `Database`'s mutating methods return this instead of an
+/// opaque error so callers (tests included) can match on the specific reason rather than just
+/// knowing that something failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerError {
+    /// `tx` was already recorded (and is still inside the retention window).
+    DuplicateTx(TxId),
+    /// No transaction — or none still inside the retention window — has this id.
+    UnknownTx(TxId),
+    /// The referenced transaction belongs to `owner`, not the client trying to dispute it.
+    WrongOwner { tx: TxId, owner: ClientId },
+    /// `client`'s account is locked following a chargeback.
+    FrozenAccount(ClientId),
+    /// Not enough available funds to cover a withdrawal, a transfer, or the hold a dispute places
+    /// on a deposit.
+    NotEnoughFunds,
+    /// A transfer's source and destination were both `client`.
+    SameClientTransfer(ClientId),
+    /// `tx` is a kind of transaction that can never be disputed.
+    NotDisputable(TxId),
+    /// A dispute targeted a transaction that wasn't `Processed`.
+    AlreadyDisputed,
+    /// A resolve or chargeback targeted a transaction that wasn't `Disputed`.
+    NotDisputed,
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::DuplicateTx(tx) => write!(f, "duplicate transaction id {}", tx),
+            LedgerError::UnknownTx(tx) => write!(f, "no such tx {}", tx),
+            LedgerError::WrongOwner { tx, owner } => {
+                write!(f, "tx {} belongs to client {}", tx, owner)
+            }
+            LedgerError::FrozenAccount(client) => write!(f, "account {} is frozen", client),
+            LedgerError::NotEnoughFunds => write!(f, "not enough funds"),
+            LedgerError::SameClientTransfer(client) => {
+                write!(f, "cannot transfer from client {} to itself", client)
+            }
+            LedgerError::NotDisputable(tx) => write!(f, "tx {} cannot be disputed", tx),
+            LedgerError::AlreadyDisputed => write!(f, "transaction is already disputed"),
+            LedgerError::NotDisputed => write!(f, "transaction is not disputed"),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
+
+/// Default number of recent transaction ids retained for duplicate-id detection and dispute
+/// lookups, absent a caller-chosen window.
+pub const DEFAULT_TX_WINDOW: usize = 1 << 16;
+
 pub struct Database {
     pub clients: HashMap<ClientId, Client>,
     pub txs: HashMap<TxId, Tx>,
+    tx_window: usize,
+    /// Ids of `txs` entries still inside the retention window, oldest first. Bounds memory by
+    /// evicting the oldest id (and its `Tx`) once a new one would push us past `tx_window`, since
+    /// withdrawals and old deposits are effectively undisputable in a streaming feed anyway.
+    recent_tx_ids: VecDeque<TxId>,
 }
 
 impl Database {
     pub fn new() -> Self {
+        Self::with_tx_window(DEFAULT_TX_WINDOW)
+    }
+
+    /// Creates a database that retains at most `tx_window` transactions for duplicate-id detection
+    /// and dispute lookups. Once a transaction ages out of the window, disputing it fails with "no
+    /// such tx", and its id becomes eligible for reuse instead of being rejected as a duplicate.
+    pub fn with_tx_window(tx_window: usize) -> Self {
         Self {
             clients: HashMap::new(),
             txs: HashMap::new(),
+            tx_window,
+            recent_tx_ids: VecDeque::new(),
+        }
+    }
+
+    /// Records `tx` under `tx_id`, evicting the oldest still-tracked transaction first if that
+    /// would push us past `tx_window`.
+    fn record_tx(&mut self, tx_id: TxId, tx: Tx) {
+        if self.recent_tx_ids.len() >= self.tx_window {
+            if let Some(oldest) = self.recent_tx_ids.pop_front() {
+                self.txs.remove(&oldest);
+            }
         }
+        self.recent_tx_ids.push_back(tx_id);
+        self.txs.insert(tx_id, tx);
     }
 
     pub fn deposit(
         &mut self,
         client_id: ClientId,
         tx_id: TxId,
+        asset: AssetId,
         amount: Decimal,
-    ) -> anyhow::Result<()> {
-        ensure!(
-            !self.txs.contains_key(&tx_id),
-            "duplicate transaction id {}",
-            tx_id
-        );
+    ) -> Result<(), LedgerError> {
+        if self.txs.contains_key(&tx_id) {
+            return Err(LedgerError::DuplicateTx(tx_id));
+        }
         let client = self.client(client_id);
+        if client.locked {
+            return Err(LedgerError::FrozenAccount(client_id));
+        }
         client
-            .deposit(amount)
-            .with_context(|| format!("failed deposit with {:?}", client))?;
-        self.txs.insert(tx_id, Tx::new(tx_id, amount));
+            .deposit(&asset, amount)
+            .expect("a deposit amount is always non-negative");
+        self.record_tx(tx_id, Tx::new(client_id, asset, amount, TxKind::Deposit));
         Ok(())
     }
 
@@ -130,43 +283,120 @@ impl Database {
         &mut self,
         client_id: ClientId,
         tx_id: TxId,
+        asset: AssetId,
         amount: Decimal,
-    ) -> anyhow::Result<()> {
-        ensure!(
-            !self.txs.contains_key(&tx_id),
-            "duplicate transaction id {}",
-            tx_id
-        );
+    ) -> Result<(), LedgerError> {
+        if self.txs.contains_key(&tx_id) {
+            return Err(LedgerError::DuplicateTx(tx_id));
+        }
         let client = self.client(client_id);
+        if client.locked {
+            return Err(LedgerError::FrozenAccount(client_id));
+        }
+        let balance = client.balances.get(&asset).copied().unwrap_or_default();
+        if amount > balance.available {
+            return Err(LedgerError::NotEnoughFunds);
+        }
         client
-            .withdraw(amount)
-            .with_context(|| format!("failed withdrawal with {:?}", client))?;
-        self.txs.insert(tx_id, Tx::new(tx_id, -amount));
+            .withdraw(&asset, amount)
+            .expect("checked available funds above");
+        self.record_tx(tx_id, Tx::new(client_id, asset, -amount, TxKind::Withdrawal));
         Ok(())
     }
 
-    pub fn dispute(&mut self, client_id: ClientId, tx_id: TxId) -> anyhow::Result<()> {
-        let (client, tx) = self.lookup(client_id, tx_id)?;
-        ensure!(tx.amount >= dec!(0), "cannot dispute a withdrawal");
-        client
-            .hold(tx.amount)
-            .with_context(|| format!("failed dispute with {:?}", client))
+    /// Atomically moves `amount` of `asset` from `from`'s `available` balance to `to`'s. Fails,
+    /// leaving both balances untouched, if either account is frozen or `from` doesn't have enough
+    /// available funds. Recorded in `txs` under `from`, sharing a deposit or withdrawal's
+    /// duplicate-id protection, but as [`TxKind::Transfer`] so it can never be disputed: the funds
+    /// are still in the ledger, now under `to`'s control, rather than having left it.
+    pub fn transfer(
+        &mut self,
+        from: ClientId,
+        to: ClientId,
+        tx_id: TxId,
+        asset: AssetId,
+        amount: Decimal,
+    ) -> Result<(), LedgerError> {
+        if self.txs.contains_key(&tx_id) {
+            return Err(LedgerError::DuplicateTx(tx_id));
+        }
+        if from == to {
+            return Err(LedgerError::SameClientTransfer(from));
+        }
+
+        let source = self.client(from);
+        if source.locked {
+            return Err(LedgerError::FrozenAccount(from));
+        }
+        let balance = source.balances.get(&asset).copied().unwrap_or_default();
+        if amount > balance.available {
+            return Err(LedgerError::NotEnoughFunds);
+        }
+
+        let dest = self.client(to);
+        if dest.locked {
+            return Err(LedgerError::FrozenAccount(to));
+        }
+
+        self.client(from)
+            .withdraw(&asset, amount)
+            .expect("checked available funds above");
+        self.client(to)
+            .deposit(&asset, amount)
+            .expect("a deposit amount is always non-negative");
+        self.record_tx(tx_id, Tx::new(from, asset, -amount, TxKind::Transfer));
+        Ok(())
+    }
+
+    pub fn dispute(&mut self, client_id: ClientId, tx_id: TxId) -> Result<(), LedgerError> {
+        let (client, tx) = self.lookup_mut(client_id, tx_id)?;
+        if client.locked {
+            return Err(LedgerError::FrozenAccount(client_id));
+        }
+        if tx.kind == TxKind::Transfer {
+            return Err(LedgerError::NotDisputable(tx_id));
+        }
+        if tx.state != TxState::Processed {
+            return Err(LedgerError::AlreadyDisputed);
+        }
+        if tx.kind == TxKind::Deposit {
+            client
+                .hold(&tx.asset, tx.amount)
+                .map_err(|_| LedgerError::NotEnoughFunds)?;
+        } else {
+            client
+                .hold_withdrawal(&tx.asset, tx.amount.abs())
+                .expect("a withdrawal's stored amount is always non-positive");
+        }
+        tx.state = TxState::Disputed;
+        Ok(())
     }
 
-    pub fn resolve(&mut self, client_id: ClientId, tx_id: TxId) -> anyhow::Result<()> {
-        let (client, tx) = self.lookup(client_id, tx_id)?;
-        ensure!(tx.amount >= dec!(0), "cannot resolve a withdrawal");
+    pub fn resolve(&mut self, client_id: ClientId, tx_id: TxId) -> Result<(), LedgerError> {
+        let (client, tx) = self.lookup_mut(client_id, tx_id)?;
+        if client.locked {
+            return Err(LedgerError::FrozenAccount(client_id));
+        }
+        if tx.state != TxState::Disputed {
+            return Err(LedgerError::NotDisputed);
+        }
         client
-            .release(tx.amount)
-            .with_context(|| format!("failed resolve with {:?}", client))
+            .release(&tx.asset, tx.amount.abs())
+            .expect("dispute already moved this amount into held");
+        tx.state = TxState::Resolved;
+        Ok(())
     }
 
-    pub fn chargeback(&mut self, client_id: ClientId, tx_id: TxId) -> anyhow::Result<()> {
-        let (client, tx) = self.lookup(client_id, tx_id)?;
-        ensure!(tx.amount >= dec!(0), "cannot chargeback a withdrawal");
+    pub fn chargeback(&mut self, client_id: ClientId, tx_id: TxId) -> Result<(), LedgerError> {
+        let (client, tx) = self.lookup_mut(client_id, tx_id)?;
+        if tx.state != TxState::Disputed {
+            return Err(LedgerError::NotDisputed);
+        }
         client
-            .chargeback(tx.amount)
-            .with_context(|| format!("failed chargeback with {:?}", client))
+            .chargeback(&tx.asset, tx.amount.abs())
+            .expect("dispute already moved this amount into held");
+        tx.state = TxState::ChargedBack;
+        Ok(())
     }
 
     /// Look up an existing client, or create a new one.
@@ -176,15 +406,29 @@ impl Database {
 
     /// We need to lookup the client and tx at the same time in order to split the borrow of `&mut
     /// self` into borrows of two sub-fields.
-    fn lookup(&mut self, client_id: ClientId, tx_id: TxId) -> anyhow::Result<(&mut Client, &Tx)> {
-        Ok((
-            self.clients
-                .get_mut(&client_id)
-                .ok_or_else(|| anyhow!("no such client {}", client_id))?,
-            self.txs
-                .get(&tx_id)
-                .ok_or_else(|| anyhow!("no such tx {}", tx_id))?,
-        ))
+    ///
+    /// Also verifies that `tx_id` actually belongs to `client_id`, so one client can't dispute,
+    /// resolve, or charge back a transaction that moved someone else's money.
+    fn lookup_mut(
+        &mut self,
+        client_id: ClientId,
+        tx_id: TxId,
+    ) -> Result<(&mut Client, &mut Tx), LedgerError> {
+        let tx = self
+            .txs
+            .get_mut(&tx_id)
+            .ok_or(LedgerError::UnknownTx(tx_id))?;
+        if tx.client != client_id {
+            return Err(LedgerError::WrongOwner {
+                tx: tx_id,
+                owner: tx.client,
+            });
+        }
+        let client = self
+            .clients
+            .get_mut(&client_id)
+            .expect("a tx's owning client always exists");
+        Ok((client, tx))
     }
 }
 
@@ -194,101 +438,349 @@ mod tests {
 
     use super::*;
 
+    fn btc() -> AssetId {
+        "BTC".to_string()
+    }
+
+    fn eth() -> AssetId {
+        "ETH".to_string()
+    }
+
     #[test]
     fn deposit_withdraw() {
         let mut db = Database::new();
 
-        db.deposit(1, 1, dec!(100)).unwrap();
-        assert_funds(&db, 1, dec!(100), dec!(0));
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(100), dec!(0));
 
-        db.deposit(1, 2, dec!(20)).unwrap();
-        assert_funds(&db, 1, dec!(120), dec!(0));
+        db.deposit(1, 2, btc(), dec!(20)).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(120), dec!(0));
 
-        db.deposit(1, 3, dec!(3)).unwrap();
-        assert_funds(&db, 1, dec!(123), dec!(0));
+        db.deposit(1, 3, btc(), dec!(3)).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(123), dec!(0));
 
-        db.withdraw(1, 4, dec!(100)).unwrap();
-        assert_funds(&db, 1, dec!(23), dec!(0));
+        db.withdraw(1, 4, btc(), dec!(100)).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(23), dec!(0));
 
-        db.withdraw(1, 5, dec!(20)).unwrap();
-        assert_funds(&db, 1, dec!(3), dec!(0));
+        db.withdraw(1, 5, btc(), dec!(20)).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(3), dec!(0));
 
-        assert!(db.withdraw(1, 6, dec!(444)).is_err());
-        assert_funds(&db, 1, dec!(3), dec!(0));
+        assert!(db.withdraw(1, 6, btc(), dec!(444)).is_err());
+        assert_funds(&db, 1, &btc(), dec!(3), dec!(0));
     }
 
     #[test]
     fn duplicate_tx_ids() {
         let mut db = Database::new();
 
-        db.deposit(1, 1, dec!(100)).unwrap();
-        assert!(db.deposit(2, 1, dec!(20)).is_err());
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        assert_eq!(
+            db.deposit(2, 1, btc(), dec!(20)),
+            Err(LedgerError::DuplicateTx(1))
+        );
+    }
+
+    #[test]
+    fn tx_window_evicts_oldest_and_allows_id_reuse() {
+        let mut db = Database::with_tx_window(2);
+
+        db.deposit(1, 1, btc(), dec!(10)).unwrap();
+        db.deposit(1, 2, btc(), dec!(10)).unwrap();
+        // Tx 3 pushes us past the window, evicting tx 1's id and `Tx`.
+        db.deposit(1, 3, btc(), dec!(10)).unwrap();
+
+        // Tx 1's id is no longer tracked as a duplicate, so it can be reused...
+        db.deposit(1, 1, btc(), dec!(10)).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(40), dec!(0));
+
+        // ...but that reuse evicted tx 2 in turn, so disputing it now fails.
+        assert!(db.dispute(1, 2).is_err());
+        // Tx 3 is still within the window and can be disputed normally.
+        db.dispute(1, 3).unwrap();
     }
 
     #[test]
     fn multiple_clients() {
         let mut db = Database::new();
 
-        db.deposit(3, 30, dec!(300)).unwrap();
-        db.deposit(2, 20, dec!(200)).unwrap();
-        db.deposit(10, 1, dec!(100)).unwrap();
-        db.withdraw(2, 21, dec!(20)).unwrap();
-        db.withdraw(10, 2, dec!(10)).unwrap();
-        db.withdraw(3, 3, dec!(30)).unwrap();
+        db.deposit(3, 30, btc(), dec!(300)).unwrap();
+        db.deposit(2, 20, btc(), dec!(200)).unwrap();
+        db.deposit(10, 1, btc(), dec!(100)).unwrap();
+        db.withdraw(2, 21, btc(), dec!(20)).unwrap();
+        db.withdraw(10, 2, btc(), dec!(10)).unwrap();
+        db.withdraw(3, 3, btc(), dec!(30)).unwrap();
+
+        assert_funds(&db, 10, &btc(), dec!(90), dec!(0));
+        assert_funds(&db, 2, &btc(), dec!(180), dec!(0));
+        assert_funds(&db, 3, &btc(), dec!(270), dec!(0));
+    }
+
+    #[test]
+    fn multiple_assets_are_independent() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.deposit(1, 2, eth(), dec!(10)).unwrap();
+        db.withdraw(1, 3, btc(), dec!(40)).unwrap();
 
-        assert_funds(&db, 10, dec!(90), dec!(0));
-        assert_funds(&db, 2, dec!(180), dec!(0));
-        assert_funds(&db, 3, dec!(270), dec!(0));
+        assert_funds(&db, 1, &btc(), dec!(60), dec!(0));
+        assert_funds(&db, 1, &eth(), dec!(10), dec!(0));
     }
 
     #[test]
     fn dispute_resolve_chargeback() {
         let mut db = Database::new();
 
-        db.deposit(1, 1, dec!(100)).unwrap();
-        db.deposit(1, 2, dec!(50)).unwrap();
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.deposit(1, 2, btc(), dec!(50)).unwrap();
 
         db.dispute(1, 1).unwrap();
-        assert_funds_locked(&db, 1, dec!(50), dec!(100), false);
+        assert_funds_locked(&db, 1, &btc(), dec!(50), dec!(100), false);
 
         db.resolve(1, 1).unwrap();
-        assert_funds_locked(&db, 1, dec!(150), dec!(0), false);
+        assert_funds_locked(&db, 1, &btc(), dec!(150), dec!(0), false);
+
+        db.dispute(1, 2).unwrap();
+        assert_funds_locked(&db, 1, &btc(), dec!(100), dec!(50), false);
+
+        db.chargeback(1, 2).unwrap();
+        assert_funds_locked(&db, 1, &btc(), dec!(100), dec!(0), true);
+    }
 
+    #[test]
+    fn dispute_operates_on_the_transactions_own_asset() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.deposit(1, 2, eth(), dec!(10)).unwrap();
+
+        db.dispute(1, 1).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(0), dec!(100));
+        assert_funds(&db, 1, &eth(), dec!(10), dec!(0));
+    }
+
+    #[test]
+    fn frozen_account_rejects_activity() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
         db.dispute(1, 1).unwrap();
-        assert_funds_locked(&db, 1, dec!(50), dec!(100), false);
+        db.chargeback(1, 1).unwrap();
+        assert_funds_locked(&db, 1, &btc(), dec!(0), dec!(0), true);
+
+        assert_eq!(
+            db.deposit(1, 2, btc(), dec!(50)),
+            Err(LedgerError::FrozenAccount(1))
+        );
+        assert_eq!(
+            db.withdraw(1, 3, btc(), dec!(10)),
+            Err(LedgerError::FrozenAccount(1))
+        );
+        assert_funds_locked(&db, 1, &btc(), dec!(0), dec!(0), true);
+    }
+
+    #[test]
+    fn frozen_account_rejects_dispute_and_resolve() {
+        let mut db = Database::new();
 
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.deposit(1, 2, btc(), dec!(50)).unwrap();
+        db.dispute(1, 1).unwrap();
         db.chargeback(1, 1).unwrap();
-        assert_funds_locked(&db, 1, dec!(50), dec!(0), true);
+        assert_funds_locked(&db, 1, &btc(), dec!(50), dec!(0), true);
+
+        // Tx 2 was never disputed, but the frozen account blocks new activity on it regardless.
+        assert_eq!(db.dispute(1, 2), Err(LedgerError::FrozenAccount(1)));
+        // Tx 1 is already `ChargedBack`, which would also fail on its own, but the frozen-account
+        // check takes priority so the caller learns the account is locked either way.
+        assert_eq!(db.resolve(1, 1), Err(LedgerError::FrozenAccount(1)));
     }
 
     #[test]
-    fn cannot_dispute_withdrawals() {
+    fn transfer_moves_funds_between_clients() {
         let mut db = Database::new();
 
-        db.deposit(1, 1, dec!(100)).unwrap();
-        db.withdraw(1, 2, dec!(60)).unwrap();
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.transfer(1, 2, 2, btc(), dec!(40)).unwrap();
 
-        assert!(db.dispute(1, 2).is_err());
-        assert!(db.resolve(1, 2).is_err());
-        assert!(db.chargeback(1, 2).is_err());
+        assert_funds(&db, 1, &btc(), dec!(60), dec!(0));
+        assert_funds(&db, 2, &btc(), dec!(40), dec!(0));
+    }
+
+    #[test]
+    fn transfer_rejects_insufficient_funds() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(10)).unwrap();
+        assert!(db.transfer(1, 2, 2, btc(), dec!(40)).is_err());
+
+        assert_funds(&db, 1, &btc(), dec!(10), dec!(0));
+        assert!(!db.clients.contains_key(&2));
+    }
+
+    #[test]
+    fn transfer_rejects_frozen_accounts() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.dispute(1, 1).unwrap();
+        db.chargeback(1, 1).unwrap();
+
+        db.deposit(3, 2, btc(), dec!(100)).unwrap();
+
+        assert!(db.transfer(1, 3, 3, btc(), dec!(10)).is_err());
+        assert!(db.transfer(3, 1, 4, btc(), dec!(10)).is_err());
+    }
+
+    #[test]
+    fn dispute_state_machine_rejects_invalid_transitions() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+
+        assert_eq!(db.resolve(1, 1), Err(LedgerError::NotDisputed));
+        assert_eq!(db.chargeback(1, 1), Err(LedgerError::NotDisputed));
+
+        db.dispute(1, 1).unwrap();
+        assert_eq!(db.dispute(1, 1), Err(LedgerError::AlreadyDisputed));
+
+        db.resolve(1, 1).unwrap();
+        assert_eq!(db.resolve(1, 1), Err(LedgerError::NotDisputed));
+        assert_eq!(db.chargeback(1, 1), Err(LedgerError::NotDisputed));
+        assert_eq!(db.dispute(1, 1), Err(LedgerError::AlreadyDisputed));
+    }
+
+    #[test]
+    fn disputed_withdrawal_resolve_returns_funds_to_available() {
+        let mut db = Database::new();
 
-        assert_funds_locked(&db, 1, dec!(40), dec!(0), false);
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.withdraw(1, 2, btc(), dec!(90)).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(10), dec!(0));
+
+        // Disputing a withdrawal credits the client by the withdrawn amount, but into `held` rather
+        // than `available`, since those funds haven't actually come back yet.
+        db.dispute(1, 2).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(10), dec!(90));
+
+        // Resolving decides the withdrawal was invalid, so the held amount returns to the client.
+        db.resolve(1, 2).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(100), dec!(0));
+    }
+
+    #[test]
+    fn disputed_withdrawal_chargeback_leaves_withdrawal_standing() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.withdraw(1, 2, btc(), dec!(90)).unwrap();
+
+        db.dispute(1, 2).unwrap();
+        assert_funds(&db, 1, &btc(), dec!(10), dec!(90));
+
+        // Charging back decides the withdrawal was legitimate after all, so the held credit is
+        // simply dropped rather than handed to the client, and the account is frozen as usual.
+        db.chargeback(1, 2).unwrap();
+        assert_funds_locked(&db, 1, &btc(), dec!(10), dec!(0), true);
+    }
+
+    #[test]
+    fn transfer_cannot_be_disputed() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.transfer(1, 2, 2, btc(), dec!(60)).unwrap();
+
+        assert_eq!(db.dispute(1, 2), Err(LedgerError::NotDisputable(2)));
+        assert_eq!(db.resolve(1, 2), Err(LedgerError::NotDisputed));
+        assert_eq!(db.chargeback(1, 2), Err(LedgerError::NotDisputed));
+
+        assert_funds_locked(&db, 1, &btc(), dec!(40), dec!(0), false);
+    }
+
+    #[test]
+    fn transfer_is_owned_by_the_sender_not_the_recipient() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.transfer(1, 2, 2, btc(), dec!(60)).unwrap();
+
+        // Tx 2 belongs to client 1, the sender, even though the money landed in client 2's
+        // account, so client 2 can't dispute it as though it were their own.
+        let wrong_owner = Err(LedgerError::WrongOwner { tx: 2, owner: 1 });
+        assert_eq!(db.dispute(2, 2), wrong_owner);
+        assert_eq!(db.resolve(2, 2), wrong_owner);
+        assert_eq!(db.chargeback(2, 2), wrong_owner);
+
+        assert_funds(&db, 1, &btc(), dec!(40), dec!(0));
+        assert_funds(&db, 2, &btc(), dec!(60), dec!(0));
+    }
+
+    #[test]
+    fn dispute_rejects_deposit_whose_funds_already_left() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.withdraw(1, 2, btc(), dec!(80)).unwrap();
+
+        // Only 20 is still available, so holding the full 100 would drive `available` negative.
+        assert_eq!(db.dispute(1, 1), Err(LedgerError::NotEnoughFunds));
+        assert_funds(&db, 1, &btc(), dec!(20), dec!(0));
+    }
+
+    #[test]
+    fn dispute_must_match_owning_client() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+        db.deposit(2, 2, btc(), dec!(200)).unwrap();
+
+        // Client 2 doesn't own tx 1, so this must be rejected rather than dipping into client 2's
+        // own available balance.
+        let wrong_owner = Err(LedgerError::WrongOwner { tx: 1, owner: 1 });
+        assert_eq!(db.dispute(2, 1), wrong_owner);
+        assert_eq!(db.resolve(2, 1), wrong_owner);
+        assert_eq!(db.chargeback(2, 1), wrong_owner);
+
+        assert_funds(&db, 1, &btc(), dec!(100), dec!(0));
+        assert_funds_locked(&db, 2, &btc(), dec!(200), dec!(0), false);
     }
 
-    fn assert_funds(db: &Database, client_id: ClientId, available: Decimal, held: Decimal) {
+    #[test]
+    fn dispute_rejects_unknown_tx() {
+        let mut db = Database::new();
+
+        db.deposit(1, 1, btc(), dec!(100)).unwrap();
+
+        assert_eq!(db.dispute(1, 99), Err(LedgerError::UnknownTx(99)));
+        assert_eq!(db.resolve(1, 99), Err(LedgerError::UnknownTx(99)));
+        assert_eq!(db.chargeback(1, 99), Err(LedgerError::UnknownTx(99)));
+
+        assert_funds(&db, 1, &btc(), dec!(100), dec!(0));
+    }
+
+    fn assert_funds(
+        db: &Database,
+        client_id: ClientId,
+        asset: &AssetId,
+        available: Decimal,
+        held: Decimal,
+    ) {
         let client = db.clients.get(&client_id).unwrap();
-        assert_eq!(client.available, available);
-        assert_eq!(client.held, held);
+        let balance = client.balances.get(asset).copied().unwrap_or_default();
+        assert_eq!(balance.available, available);
+        assert_eq!(balance.held, held);
     }
 
     fn assert_funds_locked(
         db: &Database,
         client_id: ClientId,
+        asset: &AssetId,
         available: Decimal,
         held: Decimal,
         locked: bool,
     ) {
-        assert_funds(db, client_id, available, held);
+        assert_funds(db, client_id, asset, available, held);
         let client = db.clients.get(&client_id).unwrap();
         assert_eq!(client.locked, locked);
     }
@@ -20,15 +20,23 @@ struct Args {
 fn main() -> anyhow::Result<()> {
     let args = Args::try_parse()?;
 
-    let db = process_txs(&args.file_name)?;
+    let (db, errors) = process_txs(&args.file_name)?;
+    for err in &errors {
+        eprintln!("{:?}", err);
+    }
     csv::account::write(stdout(), &db)?;
 
     Ok(())
 }
 
-fn process_txs(file_name: &Path) -> anyhow::Result<Database> {
+/// Processes every record in `file_name` into a fresh [`Database`]. Records the database rejects
+/// don't stop processing; they're collected into the returned `Vec` so the caller can report them
+/// without losing any work that did succeed. A record that fails to parse ends processing with an
+/// error.
+fn process_txs(file_name: &Path) -> anyhow::Result<(Database, Vec<anyhow::Error>)> {
     let mut db = Database::new();
-    let file = File::open(&file_name)
+    let mut errors = Vec::new();
+    let file = File::open(file_name)
         .with_context(|| format!("{}: could not open file", file_name.display()))?;
 
     for (line, record) in csv::tx::read(file).enumerate() {
@@ -37,7 +45,7 @@ fn process_txs(file_name: &Path) -> anyhow::Result<Database> {
             format!("{}:{}: error parsing CSV record", file_name.display(), line)
         })?;
 
-        match record.apply(&mut db).with_context(|| {
+        if let Err(err) = record.apply(&mut db).with_context(|| {
             format!(
                 "{}:{}: error processing {:?}",
                 file_name.display(),
@@ -45,13 +53,9 @@ fn process_txs(file_name: &Path) -> anyhow::Result<Database> {
                 record
             )
         }) {
-            Ok(()) => {}
-            Err(err) => {
-                // Ignore errors. Diagnose them but don't stop processing.
-                eprintln!("{:?}", err);
-            }
+            errors.push(err);
         }
     }
 
-    Ok(db)
+    Ok((db, errors))
 }